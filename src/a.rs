@@ -2,154 +2,150 @@ extern crate c_ares_sys;
 extern crate libc;
 
 use std::ffi::CStr;
-use std::marker::PhantomData;
 use std::mem;
 use std::net::Ipv4Addr;
 use std::ptr;
 use std::slice;
 use std::str;
+use std::vec;
 
-use types::{
-    AresError,
-    hostent,
-};
+use types::AresError;
 use utils::ares_error;
 
+/// The maximum number of addresses (and their TTLs) that we will pull out of a
+/// single reply.
+pub const MAX_ADDRTTLS: usize = 256;
+
 /// The result of a successful lookup for an A record.
+///
+/// The addresses and their TTLs are copied out of the reply while parsing, so
+/// an `AResults` owns no C-allocated memory and is cheap to hold onto - callers
+/// can keep it around to drive a cache with correct expiry.
 pub struct AResults {
-    hostent: *mut hostent,
+    hostname: String,
+    results: Vec<AResult>,
+}
+
+/// A single address from an `AResults`, carrying both the address itself and
+/// the TTL that the server attached to it.
+#[derive(Clone, Copy)]
+pub struct AResult {
+    addrttl: c_ares_sys::Struct_ares_addrttl,
 }
 
 impl AResults {
     /// Obtain an `AResults` from the response to an A lookup.
     pub fn parse_from(data: &[u8]) -> Result<AResults, AresError> {
-        let mut hostent: *mut hostent = ptr::null_mut();
+        let mut hostent: *mut c_ares_sys::Struct_hostent = ptr::null_mut();
+        let mut addrttls: [c_ares_sys::Struct_ares_addrttl; MAX_ADDRTTLS] =
+            unsafe { mem::zeroed() };
+        let mut naddrttls: libc::c_int = MAX_ADDRTTLS as libc::c_int;
         let parse_status = unsafe {
             c_ares_sys::ares_parse_a_reply(
                 data.as_ptr(),
                 data.len() as libc::c_int,
-                &mut hostent as *mut *mut _ as *mut *mut c_ares_sys::Struct_hostent,
-                ptr::null_mut(),
-                ptr::null_mut())
+                &mut hostent,
+                addrttls.as_mut_ptr(),
+                &mut naddrttls)
         };
         if parse_status != c_ares_sys::ARES_SUCCESS {
             Err(ares_error(parse_status))
         } else {
-            let result = AResults::new(hostent);
-            Ok(result)
+            // We asked for the addrttls, but c-ares still allocates a
+            // `hostent` for us.  Copy the hostname out of it and then free it
+            // straight away, so that this path doesn't have to hold onto any
+            // C-allocated memory.
+            let hostname = unsafe {
+                if hostent.is_null() {
+                    String::new()
+                } else {
+                    let c_str = CStr::from_ptr((*hostent).h_name);
+                    str::from_utf8_unchecked(c_str.to_bytes()).to_owned()
+                }
+            };
+            unsafe { c_ares_sys::ares_free_hostent(hostent); }
+            let results = addrttls[0..naddrttls as usize]
+                .iter()
+                .map(|&addrttl| AResult { addrttl: addrttl })
+                .collect();
+            Ok(AResults::new(hostname, results))
         }
     }
 
-    fn new(hostent: *mut hostent) -> AResults {
+    fn new(hostname: String, results: Vec<AResult>) -> AResults {
         AResults {
-            hostent: hostent,
+            hostname: hostname,
+            results: results,
         }
     }
 
     /// Get the hostname from this `AResults`.
     pub fn hostname(&self) -> &str {
-        unsafe {
-            let c_str = CStr::from_ptr((*self.hostent).h_name);
-            str::from_utf8_unchecked(c_str.to_bytes())
-        }
+        &self.hostname
     }
 
-    /// Returns an iterator over the `Ipv4Address` values in this `AResults`.
+    /// Returns an iterator over the `AResult` values in this `AResults`.
     pub fn iter(&self) -> AResultsIterator {
         AResultsIterator {
-            next: unsafe { (*self.hostent).h_addr_list },
-            phantom: PhantomData,
+            next: self.results.iter(),
         }
     }
 }
 
-pub struct AResultsIntoIterator {
-    next: *mut *mut libc::c_char,
+impl AResult {
+    /// Returns the IP address in this `AResult`.
+    pub fn ip_address(&self) -> Ipv4Addr {
+        // `s_addr` is in network byte order; read the bytes out directly so
+        // that we don't have to care about the host's endianness.
+        let bytes: [u8; 4] = unsafe { mem::transmute(self.addrttl.ipaddr.s_addr) };
+        Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])
+    }
 
-    // Access to the IP addresses is all through the `next` pointer, but we
-    // need to keep the AResults around so that this points to valid memory.
-    #[allow(dead_code)]
-    a_result: AResults,
+    /// Returns the TTL associated with this address, in seconds.
+    pub fn ttl(&self) -> i32 {
+        self.addrttl.ttl as i32
+    }
 }
 
-pub struct AResultsIterator<'a> {
-    next: *mut *mut libc::c_char,
+pub struct AResultsIntoIterator {
+    next: vec::IntoIter<AResult>,
+}
 
-    // We need the phantom data to make sure that the `next` pointer remains
-    // valid through the lifetime of this structure.
-    phantom: PhantomData<&'a AResults>,
+pub struct AResultsIterator<'a> {
+    next: slice::Iter<'a, AResult>,
 }
 
 impl IntoIterator for AResults {
-    type Item = Ipv4Addr;
+    type Item = AResult;
     type IntoIter = AResultsIntoIterator;
 
     fn into_iter(self) -> Self::IntoIter {
         AResultsIntoIterator {
-            next: unsafe { (*self.hostent).h_addr_list },
-            a_result: self,
+            next: self.results.into_iter(),
         }
     }
 }
 
 impl<'a> IntoIterator for &'a AResults {
-    type Item = Ipv4Addr;
+    type Item = AResult;
     type IntoIter = AResultsIterator<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        AResultsIterator {
-            next: unsafe { (*self.hostent).h_addr_list },
-            phantom: PhantomData,
-        }
+        self.iter()
     }
 }
 
-unsafe fn ipv4_addr_from_ptr(h_addr: *mut libc::c_char) -> Ipv4Addr {
-    Ipv4Addr::new(
-        *h_addr as u8,
-        *h_addr.offset(1) as u8,
-        *h_addr.offset(2) as u8,
-        *h_addr.offset(3) as u8)
-}
-
 impl Iterator for AResultsIntoIterator {
-    type Item = Ipv4Addr;
-    fn next(&mut self) -> Option<Ipv4Addr> {
-        unsafe {
-            let h_addr = *(self.next);
-            if h_addr.is_null() {
-                None
-            } else {
-                self.next = self.next.offset(1);
-                let ip_addr = ipv4_addr_from_ptr(h_addr);
-                Some(ip_addr)
-            }
-        }
+    type Item = AResult;
+    fn next(&mut self) -> Option<AResult> {
+        self.next.next()
     }
 }
 
 impl<'a> Iterator for AResultsIterator<'a> {
-    type Item = Ipv4Addr;
-    fn next(&mut self) -> Option<Ipv4Addr> {
-        unsafe {
-            let h_addr = *(self.next);
-            if h_addr.is_null() {
-                None
-            } else {
-                self.next = self.next.offset(1);
-                let ip_addr = ipv4_addr_from_ptr(h_addr);
-                Some(ip_addr)
-            }
-        }
-    }
-}
-
-impl Drop for AResults {
-    fn drop(&mut self) {
-        unsafe {
-            c_ares_sys::ares_free_hostent(
-                self.hostent as *mut c_ares_sys::Struct_hostent);
-        }
+    type Item = AResult;
+    fn next(&mut self) -> Option<AResult> {
+        self.next.next().cloned()
     }
 }
 
@@ -167,5 +163,5 @@ pub unsafe extern "C" fn query_a_callback<F>(
         AResults::parse_from(data)
     };
     let handler: Box<F> = mem::transmute(arg);
-    handler(result);
+    ::panic::catch(move || handler(result));
 }
@@ -0,0 +1,168 @@
+extern crate c_ares_sys;
+extern crate libc;
+
+use std::ffi::CStr;
+use std::mem;
+use std::net::Ipv6Addr;
+use std::ptr;
+use std::slice;
+use std::str;
+use std::vec;
+
+use a::MAX_ADDRTTLS;
+use types::AresError;
+use utils::ares_error;
+
+/// The result of a successful lookup for an AAAA record.
+///
+/// As with `AResults`, the addresses and their TTLs are copied out of the
+/// reply while parsing, so an `AAAAResults` owns no C-allocated memory.
+pub struct AAAAResults {
+    hostname: String,
+    results: Vec<AAAAResult>,
+}
+
+/// A single address from an `AAAAResults`, carrying both the address itself
+/// and the TTL that the server attached to it.
+#[derive(Clone, Copy)]
+pub struct AAAAResult {
+    addr6ttl: c_ares_sys::Struct_ares_addr6ttl,
+}
+
+impl AAAAResults {
+    /// Obtain an `AAAAResults` from the response to an AAAA lookup.
+    pub fn parse_from(data: &[u8]) -> Result<AAAAResults, AresError> {
+        let mut hostent: *mut c_ares_sys::Struct_hostent = ptr::null_mut();
+        let mut addr6ttls: [c_ares_sys::Struct_ares_addr6ttl; MAX_ADDRTTLS] =
+            unsafe { mem::zeroed() };
+        let mut naddr6ttls: libc::c_int = MAX_ADDRTTLS as libc::c_int;
+        let parse_status = unsafe {
+            c_ares_sys::ares_parse_aaaa_reply(
+                data.as_ptr(),
+                data.len() as libc::c_int,
+                &mut hostent,
+                addr6ttls.as_mut_ptr(),
+                &mut naddr6ttls)
+        };
+        if parse_status != c_ares_sys::ARES_SUCCESS {
+            Err(ares_error(parse_status))
+        } else {
+            let hostname = unsafe {
+                if hostent.is_null() {
+                    String::new()
+                } else {
+                    let c_str = CStr::from_ptr((*hostent).h_name);
+                    str::from_utf8_unchecked(c_str.to_bytes()).to_owned()
+                }
+            };
+            unsafe { c_ares_sys::ares_free_hostent(hostent); }
+            let results = addr6ttls[0..naddr6ttls as usize]
+                .iter()
+                .map(|&addr6ttl| AAAAResult { addr6ttl: addr6ttl })
+                .collect();
+            Ok(AAAAResults::new(hostname, results))
+        }
+    }
+
+    fn new(hostname: String, results: Vec<AAAAResult>) -> AAAAResults {
+        AAAAResults {
+            hostname: hostname,
+            results: results,
+        }
+    }
+
+    /// Get the hostname from this `AAAAResults`.
+    pub fn hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    /// Returns an iterator over the `AAAAResult` values in this `AAAAResults`.
+    pub fn iter(&self) -> AAAAResultsIterator {
+        AAAAResultsIterator {
+            next: self.results.iter(),
+        }
+    }
+}
+
+impl AAAAResult {
+    /// Returns the IP address in this `AAAAResult`.
+    pub fn ip_address(&self) -> Ipv6Addr {
+        // The `in6_addr` is just sixteen bytes in network (big-endian) order;
+        // rebuild the eight segments from successive byte pairs so that we
+        // don't assume host byte order.
+        let bytes: [u8; 16] = unsafe { mem::transmute(self.addr6ttl.ip6addr) };
+        Ipv6Addr::new(
+            ((bytes[0] as u16) << 8) | bytes[1] as u16,
+            ((bytes[2] as u16) << 8) | bytes[3] as u16,
+            ((bytes[4] as u16) << 8) | bytes[5] as u16,
+            ((bytes[6] as u16) << 8) | bytes[7] as u16,
+            ((bytes[8] as u16) << 8) | bytes[9] as u16,
+            ((bytes[10] as u16) << 8) | bytes[11] as u16,
+            ((bytes[12] as u16) << 8) | bytes[13] as u16,
+            ((bytes[14] as u16) << 8) | bytes[15] as u16)
+    }
+
+    /// Returns the TTL associated with this address, in seconds.
+    pub fn ttl(&self) -> i32 {
+        self.addr6ttl.ttl as i32
+    }
+}
+
+pub struct AAAAResultsIntoIterator {
+    next: vec::IntoIter<AAAAResult>,
+}
+
+pub struct AAAAResultsIterator<'a> {
+    next: slice::Iter<'a, AAAAResult>,
+}
+
+impl IntoIterator for AAAAResults {
+    type Item = AAAAResult;
+    type IntoIter = AAAAResultsIntoIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        AAAAResultsIntoIterator {
+            next: self.results.into_iter(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a AAAAResults {
+    type Item = AAAAResult;
+    type IntoIter = AAAAResultsIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Iterator for AAAAResultsIntoIterator {
+    type Item = AAAAResult;
+    fn next(&mut self) -> Option<AAAAResult> {
+        self.next.next()
+    }
+}
+
+impl<'a> Iterator for AAAAResultsIterator<'a> {
+    type Item = AAAAResult;
+    fn next(&mut self) -> Option<AAAAResult> {
+        self.next.next().cloned()
+    }
+}
+
+pub unsafe extern "C" fn query_aaaa_callback<F>(
+    arg: *mut libc::c_void,
+    status: libc::c_int,
+    _timeouts: libc::c_int,
+    abuf: *mut libc::c_uchar,
+    alen: libc::c_int)
+    where F: FnOnce(Result<AAAAResults, AresError>) + 'static {
+    let result = if status != c_ares_sys::ARES_SUCCESS {
+        Err(ares_error(status))
+    } else {
+        let data = slice::from_raw_parts(abuf, alen as usize);
+        AAAAResults::parse_from(data)
+    };
+    let handler: Box<F> = mem::transmute(arg);
+    ::panic::catch(move || handler(result));
+}
@@ -0,0 +1,182 @@
+use std::net::IpAddr;
+use std::vec;
+
+use a::AResults;
+use aaaa::AAAAResults;
+use types::AresError;
+
+/// Which address family to offer first when merging an A and an AAAA reply.
+///
+/// This backs Happy-Eyeballs-style connection ordering: a caller that wants to
+/// race IPv4 and IPv6 connections controls which family it reaches for first
+/// through this knob.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FamilyPreference {
+    /// IPv4 addresses first, then IPv6.
+    V4First,
+    /// IPv6 addresses first, then IPv4.
+    V6First,
+    /// Leave the addresses in the order the replies were parsed (A then AAAA),
+    /// with no reordering.
+    AsReturned,
+    /// Interleave the two families round-robin so that neither is starved -
+    /// the getaddrinfo-style ordering Happy-Eyeballs wants.
+    Interleave,
+}
+
+/// A single merged address, carrying the address itself and its TTL.
+#[derive(Clone, Copy)]
+pub struct CombinedResult {
+    address: IpAddr,
+    ttl: i32,
+}
+
+/// The merged result of an A and an AAAA lookup for the same name.
+///
+/// The two replies are joined into one TTL-aware address list - the moral
+/// equivalent of what `getaddrinfo` hands back - with each record keeping the
+/// TTL from its originating `ares_addrttl` / `ares_addr6ttl`.
+pub struct CombinedResults {
+    results: Vec<CombinedResult>,
+}
+
+impl CombinedResults {
+    /// Merge an A and an AAAA reply for the same name into a single address
+    /// list, ordered according to `preference`.
+    ///
+    /// This only merges two replies that the caller has *already* obtained -
+    /// it does not issue any queries itself.  Fire `query_a_callback` and
+    /// `query_aaaa_callback` for the name, then hand the two `Result`s here.
+    /// If either lookup failed the other is still used, so a name that resolves
+    /// over only one family still yields addresses; the merge only fails if
+    /// both lookups did.
+    pub fn merge(
+        a: Result<AResults, AresError>,
+        aaaa: Result<AAAAResults, AresError>,
+        preference: FamilyPreference) -> Result<CombinedResults, AresError> {
+        // Only fail if both lookups failed: a lookup that succeeds with zero
+        // records is a valid (empty) answer, not an error.
+        if a.is_err() && aaaa.is_err() {
+            return Err(a.err().or_else(|| aaaa.err()).unwrap());
+        }
+
+        let v4: Vec<CombinedResult> = match a {
+            Ok(ref results) => results
+                .iter()
+                .map(|r| CombinedResult {
+                    address: IpAddr::V4(r.ip_address()),
+                    ttl: r.ttl(),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        let v6: Vec<CombinedResult> = match aaaa {
+            Ok(ref results) => results
+                .iter()
+                .map(|r| CombinedResult {
+                    address: IpAddr::V6(r.ip_address()),
+                    ttl: r.ttl(),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        let results = match preference {
+            FamilyPreference::V4First => {
+                v4.into_iter().chain(v6.into_iter()).collect()
+            }
+            FamilyPreference::V6First => {
+                v6.into_iter().chain(v4.into_iter()).collect()
+            }
+            FamilyPreference::AsReturned => {
+                v4.into_iter().chain(v6.into_iter()).collect()
+            }
+            FamilyPreference::Interleave => interleave(v4, v6),
+        };
+        Ok(CombinedResults { results: results })
+    }
+
+    /// Returns an iterator over the merged addresses.
+    pub fn iter(&self) -> CombinedResultsIterator {
+        CombinedResultsIterator {
+            next: self.results.iter(),
+        }
+    }
+}
+
+// Interleave two address lists round-robin, so that a caller draining the
+// result gets addresses from both families early rather than all of one then
+// all of the other.
+fn interleave(
+    v4: Vec<CombinedResult>,
+    v6: Vec<CombinedResult>) -> Vec<CombinedResult> {
+    let mut merged = Vec::with_capacity(v4.len() + v6.len());
+    let mut v4 = v4.into_iter();
+    let mut v6 = v6.into_iter();
+    loop {
+        match (v4.next(), v6.next()) {
+            (Some(a), Some(b)) => {
+                merged.push(a);
+                merged.push(b);
+            }
+            (Some(a), None) => merged.push(a),
+            (None, Some(b)) => merged.push(b),
+            (None, None) => break,
+        }
+    }
+    merged
+}
+
+impl CombinedResult {
+    /// Returns the IP address in this `CombinedResult`.
+    pub fn ip_address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// Returns the TTL associated with this address, in seconds.
+    pub fn ttl(&self) -> i32 {
+        self.ttl
+    }
+}
+
+pub struct CombinedResultsIntoIterator {
+    next: vec::IntoIter<CombinedResult>,
+}
+
+pub struct CombinedResultsIterator<'a> {
+    next: ::std::slice::Iter<'a, CombinedResult>,
+}
+
+impl IntoIterator for CombinedResults {
+    type Item = CombinedResult;
+    type IntoIter = CombinedResultsIntoIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CombinedResultsIntoIterator {
+            next: self.results.into_iter(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a CombinedResults {
+    type Item = CombinedResult;
+    type IntoIter = CombinedResultsIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Iterator for CombinedResultsIntoIterator {
+    type Item = CombinedResult;
+    fn next(&mut self) -> Option<CombinedResult> {
+        self.next.next()
+    }
+}
+
+impl<'a> Iterator for CombinedResultsIterator<'a> {
+    type Item = CombinedResult;
+    fn next(&mut self) -> Option<CombinedResult> {
+        self.next.next().cloned()
+    }
+}
@@ -0,0 +1,38 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic;
+
+thread_local! {
+    // A panic caught while running a user closure inside a C callback.  We hold
+    // onto it here rather than letting it unwind across the c-ares C stack
+    // frame, which would be undefined behavior.
+    static CAUGHT_PANIC: RefCell<Option<Box<Any + Send>>> = RefCell::new(None);
+}
+
+/// Run a user closure from inside an `extern "C"` callback, catching any panic
+/// so that it doesn't unwind across the C boundary.
+///
+/// `FnOnce` is not `UnwindSafe`, so we assert it here; the first caught panic
+/// is stashed away to be re-raised by `propagate` the next time the driver is
+/// polled on the Rust side.
+pub fn catch<F>(f: F) where F: FnOnce() {
+    if let Err(panic) = panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        CAUGHT_PANIC.with(|slot| {
+            let mut slot = slot.borrow_mut();
+            if slot.is_none() {
+                *slot = Some(panic);
+            }
+        });
+    }
+}
+
+/// Re-raise a panic previously caught in a callback, if there is one.
+///
+/// The driver calls this when the user next polls, so that a panic which
+/// originated in a callback surfaces on the Rust side instead of being lost.
+pub fn propagate() {
+    let caught = CAUGHT_PANIC.with(|slot| slot.borrow_mut().take());
+    if let Some(panic) = caught {
+        panic::resume_unwind(panic);
+    }
+}
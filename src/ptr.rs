@@ -0,0 +1,140 @@
+extern crate c_ares_sys;
+extern crate libc;
+
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::mem;
+use std::net::IpAddr;
+use std::ptr;
+use std::slice;
+use std::str;
+
+use types::AresError;
+use utils::ares_error;
+
+/// The result of a successful lookup for a PTR record.
+///
+/// This is the reverse of what `AResults` does: it turns an in-addr.arpa /
+/// ip6.arpa response back into the hostnames that the address maps to.
+pub struct PTRResults {
+    hostent: *mut c_ares_sys::Struct_hostent,
+}
+
+impl PTRResults {
+    /// Obtain a `PTRResults` from the response to a PTR lookup.
+    ///
+    /// `address` is the address that was originally queried; `ares_parse_ptr_reply`
+    /// needs it alongside the reply buffer.
+    pub fn parse_from(
+        data: &[u8],
+        address: IpAddr) -> Result<PTRResults, AresError> {
+        let mut hostent: *mut c_ares_sys::Struct_hostent = ptr::null_mut();
+        let parse_status = match address {
+            IpAddr::V4(v4) => {
+                let octets = v4.octets();
+                unsafe {
+                    c_ares_sys::ares_parse_ptr_reply(
+                        data.as_ptr(),
+                        data.len() as libc::c_int,
+                        octets.as_ptr() as *const libc::c_void,
+                        octets.len() as libc::c_int,
+                        libc::AF_INET,
+                        &mut hostent)
+                }
+            }
+            IpAddr::V6(v6) => {
+                let octets = v6.octets();
+                unsafe {
+                    c_ares_sys::ares_parse_ptr_reply(
+                        data.as_ptr(),
+                        data.len() as libc::c_int,
+                        octets.as_ptr() as *const libc::c_void,
+                        octets.len() as libc::c_int,
+                        libc::AF_INET6,
+                        &mut hostent)
+                }
+            }
+        };
+        if parse_status != c_ares_sys::ARES_SUCCESS {
+            Err(ares_error(parse_status))
+        } else {
+            Ok(PTRResults::new(hostent))
+        }
+    }
+
+    fn new(hostent: *mut c_ares_sys::Struct_hostent) -> PTRResults {
+        PTRResults {
+            hostent: hostent,
+        }
+    }
+
+    /// Returns an iterator over the hostnames (aliases) in this `PTRResults`.
+    pub fn iter(&self) -> PTRResultsIterator {
+        PTRResultsIterator {
+            next: unsafe { (*self.hostent).h_aliases },
+            phantom: PhantomData,
+        }
+    }
+}
+
+pub struct PTRResultsIterator<'a> {
+    next: *mut *mut libc::c_char,
+
+    // We need the phantom data to make sure that the `next` pointer remains
+    // valid through the lifetime of this structure.
+    phantom: PhantomData<&'a PTRResults>,
+}
+
+impl<'a> IntoIterator for &'a PTRResults {
+    type Item = &'a str;
+    type IntoIter = PTRResultsIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for PTRResultsIterator<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<&'a str> {
+        unsafe {
+            let alias = *(self.next);
+            if alias.is_null() {
+                None
+            } else {
+                self.next = self.next.offset(1);
+                let c_str = CStr::from_ptr(alias);
+                Some(str::from_utf8_unchecked(c_str.to_bytes()))
+            }
+        }
+    }
+}
+
+impl Drop for PTRResults {
+    fn drop(&mut self) {
+        unsafe {
+            c_ares_sys::ares_free_hostent(self.hostent);
+        }
+    }
+}
+
+pub unsafe extern "C" fn query_ptr_callback<F>(
+    arg: *mut libc::c_void,
+    status: libc::c_int,
+    _timeouts: libc::c_int,
+    abuf: *mut libc::c_uchar,
+    alen: libc::c_int)
+    where F: FnOnce(Result<PTRResults, AresError>) + 'static {
+    // Unlike the A and AAAA paths, `ares_parse_ptr_reply` needs the address
+    // that was originally queried, which c-ares doesn't hand back to the
+    // callback.  The driver therefore boxes it up alongside the handler.
+    let boxed: Box<(IpAddr, F)> = mem::transmute(arg);
+    let (address, handler) = *boxed;
+    let result = if status != c_ares_sys::ARES_SUCCESS {
+        Err(ares_error(status))
+    } else {
+        let data = slice::from_raw_parts(abuf, alen as usize);
+        PTRResults::parse_from(data, address)
+    };
+    ::panic::catch(move || handler(result));
+}